@@ -1,10 +1,11 @@
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use mutex::Mutex;
 use process::{Process, State, Id};
 use traps::TrapFrame;
 use pi::interrupt::{Interrupt, Controller};
-use pi::timer::tick_in;
+use pi::timer::{tick_in, current_time};
 use aarch64;
 use run_blinky;
 use run_shell;
@@ -12,36 +13,492 @@ use run_shell;
 /// The `tick` time.
 pub const TICK: u32 = 10 * 1000;
 
-/// Process scheduler for the entire machine.
+/// Number of CPU cores the machine schedules across. The Raspberry Pi 3 has
+/// four Cortex-A53 cores.
+pub const NUM_CORES: usize = 4;
+
+/// Number of priority levels understood by policies that rank processes.
+/// Level `0` is the highest priority.
+pub const NUM_LEVELS: usize = 4;
+
+/// Number of `next` decisions between priority boosts in the multilevel
+/// feedback policy. Every `BOOST_INTERVAL` dispatches every process is moved
+/// back into the top queue so a long-running CPU hog cannot starve the levels
+/// below it indefinitely.
+pub const BOOST_INTERVAL: u64 = 64;
+
+/// Initial scheduling priority a process is created with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    High,
+    Low,
+}
+
+impl Priority {
+    /// The priority level this priority maps onto.
+    fn level(self) -> usize {
+        match self {
+            Priority::High => 0,
+            Priority::Low => NUM_LEVELS - 1,
+        }
+    }
+}
+
+/// A minimal xorshift PRNG, used to pick a random victim core when stealing
+/// work. Deterministic and allocation-free — each core seeds its own instance
+/// so their choices stay uncorrelated.
+#[derive(Debug)]
+struct XorShiftRng {
+    state: u32,
+}
+
+impl XorShiftRng {
+    /// Seeds a generator. The seed is forced non-zero, as xorshift is stuck at
+    /// zero otherwise.
+    fn new(seed: u32) -> XorShiftRng {
+        XorShiftRng { state: seed | 1 }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+}
+
+/// Decides the *order* in which ready processes run. `Scheduler` owns the
+/// process storage and the context-switch mechanics; a `SchedulingPolicy`
+/// owns only the bookkeeping needed to pick the next process, so the
+/// save/restore path stays identical no matter how processes are ranked.
+///
+/// Modeled on the old `Runtime` trait that abstracted the single dispatch
+/// point between 1:1 and M:N scheduling.
+pub trait SchedulingPolicy: Send + ::std::fmt::Debug {
+    /// Records that process `id` has entered the run set. `p` is provided so a
+    /// policy can read scheduling hints such as `priority`.
+    fn enqueue(&mut self, id: Id, p: &Process);
+
+    /// Returns the next process to run, or `None` if the policy has nothing it
+    /// wants to dispatch. `ready` reports whether a given process is currently
+    /// runnable (not sleeping or blocked); a policy must skip — but not
+    /// forget — processes for which `ready` returns `false`. `ready` takes
+    /// `&mut` because polling a `State::Waiting` event closure (and
+    /// `Process::is_ready`) needs `&mut self`.
+    fn next(&mut self, ready: &mut dyn FnMut(Id) -> bool) -> Option<Id>;
+
+    /// Notes that process `id` was preempted by the timer having exhausted its
+    /// slice. Policies that penalize CPU-bound processes react here.
+    fn on_preempt(&mut self, id: Id);
+
+    /// Drops process `id` from the policy's bookkeeping. Called when a process
+    /// is migrated to another core's run queue by work-stealing.
+    fn remove(&mut self, id: Id);
+}
+
+/// The policy a `GlobalScheduler` should run with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    Mlfq,
+    RoundRobin,
+    Stride,
+}
+
+impl Policy {
+    fn build(self) -> Box<dyn SchedulingPolicy> {
+        match self {
+            Policy::Mlfq => Box::new(MlfqPolicy::new()),
+            Policy::RoundRobin => Box::new(RoundRobinPolicy::new()),
+            Policy::Stride => Box::new(StridePolicy::new()),
+        }
+    }
+}
+
+/// Multilevel feedback ordering. Processes are ranked across `NUM_LEVELS`
+/// ready queues (level `0` highest); `next` always dispatches from the highest
+/// non-empty level, round-robin within it. A process preempted by the timer
+/// having exhausted its slice is demoted one level on `on_preempt`, while a
+/// process that yields or blocks early keeps its level. Every `BOOST_INTERVAL`
+/// dispatches all processes are boosted back to the top queue to prevent the
+/// lower levels from starving.
 #[derive(Debug)]
-pub struct GlobalScheduler(Mutex<Option<Scheduler>>);
+struct MlfqPolicy {
+    levels: Vec<VecDeque<Id>>,
+    dispatches: u64,
+}
+
+impl MlfqPolicy {
+    fn new() -> MlfqPolicy {
+        MlfqPolicy {
+            levels: (0..NUM_LEVELS).map(|_| VecDeque::new()).collect(),
+            dispatches: 0,
+        }
+    }
+
+    /// Moves every queued process back into the top queue.
+    fn boost(&mut self) {
+        for level in 1..NUM_LEVELS {
+            while let Some(id) = self.levels[level].pop_front() {
+                self.levels[0].push_back(id);
+            }
+        }
+    }
+}
+
+impl SchedulingPolicy for MlfqPolicy {
+    fn enqueue(&mut self, id: Id, p: &Process) {
+        let level = p.priority.min(NUM_LEVELS - 1);
+        self.levels[level].push_back(id);
+    }
+
+    fn next(&mut self, ready: &mut dyn FnMut(Id) -> bool) -> Option<Id> {
+        self.dispatches += 1;
+        if self.dispatches % BOOST_INTERVAL == 0 {
+            self.boost();
+        }
+
+        for level in 0..NUM_LEVELS {
+            if let Some(pos) = self.levels[level].iter().position(|&id| ready(id)) {
+                let id = self.levels[level].remove(pos).unwrap();
+                self.levels[level].push_back(id);
+                return Some(id);
+            }
+        }
+        None
+    }
+
+    fn on_preempt(&mut self, id: Id) {
+        // The process showed itself CPU-bound by consuming its whole slice;
+        // demote it one level so latency-sensitive work is serviced first.
+        for level in 0..NUM_LEVELS {
+            if let Some(pos) = self.levels[level].iter().position(|&other| other == id) {
+                let id = self.levels[level].remove(pos).unwrap();
+                let next_level = (level + 1).min(NUM_LEVELS - 1);
+                self.levels[next_level].push_back(id);
+                return;
+            }
+        }
+    }
+
+    fn remove(&mut self, id: Id) {
+        for level in self.levels.iter_mut() {
+            level.retain(|&other| other != id);
+        }
+    }
+}
+
+/// Flat round-robin ordering: the original scheduler's policy, FIFO with each
+/// dispatched process rotated to the back.
+#[derive(Debug)]
+struct RoundRobinPolicy {
+    order: VecDeque<Id>,
+}
+
+impl RoundRobinPolicy {
+    fn new() -> RoundRobinPolicy {
+        RoundRobinPolicy { order: VecDeque::new() }
+    }
+}
+
+impl SchedulingPolicy for RoundRobinPolicy {
+    fn enqueue(&mut self, id: Id, _p: &Process) {
+        self.order.push_back(id);
+    }
+
+    fn next(&mut self, ready: &mut dyn FnMut(Id) -> bool) -> Option<Id> {
+        let pos = self.order.iter().position(|&id| ready(id))?;
+        let id = self.order.remove(pos).unwrap();
+        self.order.push_back(id);
+        Some(id)
+    }
+
+    fn on_preempt(&mut self, _id: Id) {}
+
+    fn remove(&mut self, id: Id) {
+        self.order.retain(|&other| other != id);
+    }
+}
+
+/// Deterministic proportional-share (stride) scheduling. Each process is
+/// handed a number of tickets derived from its `priority`; the process with
+/// the smallest accumulated `pass` runs next and then advances its `pass` by
+/// its stride, so higher-ticket processes are dispatched proportionally more
+/// often without the variance of a lottery draw.
+#[derive(Debug)]
+struct StridePolicy {
+    entries: Vec<StrideEntry>,
+}
+
+#[derive(Debug)]
+struct StrideEntry {
+    id: Id,
+    stride: u64,
+    pass: u64,
+}
+
+/// Large dividend so integer strides stay well separated across ticket counts.
+const STRIDE1: u64 = 1 << 20;
+
+impl StridePolicy {
+    fn new() -> StridePolicy {
+        StridePolicy { entries: Vec::new() }
+    }
+
+    fn tickets(p: &Process) -> u64 {
+        // Higher-priority (lower level) processes get more tickets.
+        (NUM_LEVELS - p.priority.min(NUM_LEVELS - 1)) as u64
+    }
+}
+
+impl SchedulingPolicy for StridePolicy {
+    fn enqueue(&mut self, id: Id, p: &Process) {
+        let tickets = StridePolicy::tickets(p).max(1);
+        // New arrivals pass at the current minimum so they neither starve nor
+        // monopolize the core on entry.
+        let pass = self.entries.iter().map(|e| e.pass).min().unwrap_or(0);
+        self.entries.push(StrideEntry { id, stride: STRIDE1 / tickets, pass });
+    }
+
+    fn next(&mut self, ready: &mut dyn FnMut(Id) -> bool) -> Option<Id> {
+        let idx = self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| ready(e.id))
+            .min_by_key(|(_, e)| e.pass)
+            .map(|(i, _)| i)?;
+        let entry = &mut self.entries[idx];
+        entry.pass += entry.stride;
+        Some(entry.id)
+    }
+
+    fn on_preempt(&mut self, _id: Id) {}
+
+    fn remove(&mut self, id: Id) {
+        self.entries.retain(|e| e.id != id);
+    }
+}
+
+/// Process scheduler for the entire machine. Holds one local `Scheduler` per
+/// CPU core, each guarded by its own `Mutex`, plus a machine-wide ID counter
+/// so `tpidr` allocation stays unique across cores.
+///
+/// The per-core queues and random work-stealing are fully wired, but the
+/// current boot path (`start_with`) only releases the boot core into `switch`;
+/// bringing the secondary cores online is a separate change. Until then the
+/// stealing path is dormant — see the note in `start_with`.
+pub struct GlobalScheduler {
+    cores: [Mutex<Option<Scheduler>>; NUM_CORES],
+    next_id: AtomicUsize,
+}
 
 impl GlobalScheduler {
-    /// Returns an uninitialized wrapper around a local scheduler.
+    /// Returns an uninitialized wrapper around the per-core schedulers.
     pub const fn uninitialized() -> GlobalScheduler {
-        GlobalScheduler(Mutex::new(None))
+        GlobalScheduler {
+            cores: [
+                Mutex::new(None),
+                Mutex::new(None),
+                Mutex::new(None),
+                Mutex::new(None),
+            ],
+            next_id: AtomicUsize::new(0),
+        }
     }
 
-    /// Adds a process to the scheduler's queue and returns that process's ID.
-    /// For more details, see the documentation on `Scheduler::add()`.
+    /// Atomically allocates a fresh, machine-wide unique process ID.
+    fn alloc_id(&self) -> Id {
+        self.next_id.fetch_add(1, Ordering::SeqCst) as Id
+    }
+
+    /// Adds a process to the scheduler and returns that process's ID. For more
+    /// details, see the documentation on `Scheduler::add_process()`.
     pub fn add(&self, process: Process) -> Option<Id> {
-        self.0.lock().as_mut().expect("scheduler uninitialized").add(process)
+        self.add_with_priority(process, Priority::High)
+    }
+
+    /// Adds a process at the given `priority`, load-balancing it onto the core
+    /// with the shortest run queue, and returns that process's ID.
+    pub fn add_with_priority(&self, mut process: Process, priority: Priority) -> Option<Id> {
+        process.priority = priority.level();
+
+        // Place the newcomer on whichever core currently has the least work.
+        let mut target = 0;
+        let mut shortest = usize::max_value();
+        for i in 0..NUM_CORES {
+            if let Some(sched) = self.cores[i].lock().as_ref() {
+                if sched.processes.len() < shortest {
+                    shortest = sched.processes.len();
+                    target = i;
+                }
+            }
+        }
+
+        self.add_on_core(target, process, priority)
     }
 
-    /// Performs a context switch using `tf` by setting the state of the current
-    /// process to `new_state`, saving `tf` into the current process, and
-    /// restoring the next process's trap frame into `tf`. For more details, see
-    /// the documentation on `Scheduler::switch()`.
+    /// Adds a process at the given `priority` to the run queue of a specific
+    /// `core`, returning its ID. Unlike `add_with_priority` this bypasses
+    /// load-balancing, so the boot path can pin its initial processes to the
+    /// only core that is actually live. Work-stealing redistributes them once
+    /// secondary cores come up.
+    fn add_on_core(&self, core: usize, mut process: Process, priority: Priority) -> Option<Id> {
+        process.priority = priority.level();
+        let id = self.alloc_id();
+        self.cores[core].lock().as_mut().expect("scheduler uninitialized").add_process(id, process)
+    }
+
+    /// Performs a context switch on the current core using `tf`, delegating to
+    /// that core's local `Scheduler`. When the local queue has nothing ready to
+    /// run, the core steals work from a randomly chosen victim before falling
+    /// back to `wfi`. For more details, see the documentation on
+    /// `Scheduler::park_current()` and `Scheduler::pick()`.
     #[must_use]
     pub fn switch(&self, new_state: State, tf: &mut TrapFrame) -> Option<Id> {
-        self.0.lock().as_mut().expect("scheduler uninitialized").switch(new_state, tf)
+        let core = aarch64::affinity() as usize;
+
+        // Save the outgoing process back into this core's queue exactly once.
+        // A core with no current process (e.g. one that has just come online
+        // with an empty queue) still has to fall through to `steal`/`wfi`, so
+        // guard the park rather than bailing out of `switch` on `None`.
+        {
+            let mut guard = self.cores[core].lock();
+            let sched = guard.as_mut().expect("scheduler uninitialized");
+            if sched.current.is_some() {
+                sched.park_current(new_state, tf);
+            }
+        }
+
+        loop {
+            // Try to dispatch something from the local queue.
+            {
+                let mut guard = self.cores[core].lock();
+                let sched = guard.as_mut().expect("scheduler uninitialized");
+                if let Some(id) = sched.pick(tf) {
+                    return Some(id);
+                }
+            }
+
+            // Local queue has nothing ready; try to steal before idling.
+            if self.steal(core) {
+                continue;
+            }
+
+            // No other core had work to give. Wake any due sleepers first —
+            // otherwise a sleeper that just came due yields `until - now == 0`
+            // and the timer is armed for its minimal delay, spinning the idle
+            // loop instead of idling. If one
+            // woke, retry the dispatch before idling; else arm for the nearest
+            // sleeper deadline and idle.
+            {
+                let mut guard = self.cores[core].lock();
+                if let Some(sched) = guard.as_mut() {
+                    sched.wake_sleepers();
+                    if sched.has_ready() {
+                        continue;
+                    }
+                    sched.arm_timer();
+                }
+            }
+            aarch64::wfi();
+        }
+    }
+
+    /// Attempts to steal the back half of another core's run queue into
+    /// `core`'s queue, trying each other core once, starting from a randomly
+    /// chosen victim. Returns `true` if any process was moved.
+    fn steal(&self, core: usize) -> bool {
+        // Pick a starting victim distinct from `core` using this core's own RNG.
+        let r = {
+            let mut guard = self.cores[core].lock();
+            let sched = match guard.as_mut() {
+                Some(sched) => sched,
+                None => return false,
+            };
+            sched.rng.next_u32() as usize
+        };
+        (0..NUM_CORES - 1)
+            .map(|k| (core + 1 + (r + k) % (NUM_CORES - 1)) % NUM_CORES)
+            .any(|victim| self.steal_from(core, victim))
+    }
+
+    /// Steals the back half of `victim`'s run queue into `core`'s queue.
+    /// Returns `true` if any process was moved.
+    ///
+    /// To avoid deadlock the two core mutexes are always acquired in ascending
+    /// index order regardless of which is the thief and which the victim.
+    fn steal_from(&self, core: usize, victim: usize) -> bool {
+        let (lo, hi) = if core < victim { (core, victim) } else { (victim, core) };
+        let mut guard_lo = self.cores[lo].lock();
+        let mut guard_hi = self.cores[hi].lock();
+
+        // Resolve which guard is the thief and which the victim.
+        let (thief, donor) = if core == lo {
+            (guard_lo.as_mut(), guard_hi.as_mut())
+        } else {
+            (guard_hi.as_mut(), guard_lo.as_mut())
+        };
+        let (thief, donor) = match (thief, donor) {
+            (Some(thief), Some(donor)) => (thief, donor),
+            _ => return false,
+        };
+
+        let stolen = donor.take_back_half();
+        if stolen.is_empty() {
+            return false;
+        }
+        for process in stolen {
+            thief.absorb(process);
+        }
+        true
+    }
+
+    /// Spawns a child process on the current core that resumes in the same
+    /// place as the parent whose trap frame is `tf`. The child is given a
+    /// fresh, independent stack holding a copy of the parent's live frame, so
+    /// both halves run the same code on distinct stacks. The parent observes
+    /// the child's ID as the return value; the child observes `0`. Intended to
+    /// be called from the trap handler servicing a `fork` request. Returns the
+    /// new child's ID, or `None` if no process is running or no further
+    /// processes can be scheduled.
+    #[must_use]
+    pub fn fork(&self, tf: &TrapFrame) -> Option<Id> {
+        let core = aarch64::affinity() as usize;
+        let id = self.alloc_id();
+        self.cores[core].lock().as_mut().expect("scheduler uninitialized").fork(id, tf)
+    }
+
+    /// Puts the current process to sleep for at least `ms` milliseconds and
+    /// context switches away from it. The process becomes `Ready` again once
+    /// the system timer passes the computed deadline. Intended to be called
+    /// from the trap handler servicing a `sleep` request. Returns the ID of the
+    /// process switched to, as in `switch`.
+    #[must_use]
+    pub fn sleep(&self, ms: u32, tf: &mut TrapFrame) -> Option<Id> {
+        let until = current_time() + (ms as u64) * 1000;
+        self.switch(State::Sleeping { until }, tf)
     }
 
-    /// Initializes the scheduler and starts executing processes in user space
-    /// using timer interrupt based preemptive scheduling. This method should
-    /// not return under normal conditions.
+    /// Initializes the scheduler with the multilevel feedback policy and starts
+    /// executing processes. This keeps the latency-sensitive shell serviced
+    /// ahead of CPU-bound work (honoring `add_with_priority`) with
+    /// anti-starvation boosting. See `start_with` for the general entry point.
     pub fn start(&self) {
-        *self.0.lock() = Some(Scheduler::new());
+        self.start_with(Policy::Mlfq)
+    }
+
+    /// Initializes a local scheduler on every core with the selected `policy`
+    /// and starts executing processes in user space using timer interrupt
+    /// based preemptive scheduling. This method should not return under normal
+    /// conditions.
+    pub fn start_with(&self, policy: Policy) {
+        for i in 0..NUM_CORES {
+            // Distinct, non-zero seeds keep the per-core victim choices
+            // uncorrelated.
+            let seed = (i as u32).wrapping_mul(0x9e37_79b9) ^ 0x5bd1_e995;
+            *self.cores[i].lock() = Some(Scheduler::new(policy.build(), seed));
+        }
 
         let mut process = Process::new().expect("First process failed");
         process.trap_frame.elr = run_shell as u64;
@@ -50,12 +507,24 @@ impl GlobalScheduler {
         process.trap_frame.spsr = 0x0;
         let tf = process.trap_frame.clone();
 
-        self.add(process);
+        // Only the boot core is released into `switch` below; the secondary
+        // cores stay spinning. Pin both initial processes to the boot core so
+        // blinky is actually scheduled rather than load-balanced onto a core
+        // that never runs. Keep the latency-sensitive shell above blinky.
+        let boot = aarch64::affinity() as usize;
+        self.add_on_core(boot, process, Priority::High);
 
         let mut process_1 = Process::new().unwrap();
         process_1.trap_frame.elr = run_blinky as u64;
         process_1.trap_frame.sp = process_1.stack.top().as_u64();
-        self.add(process_1);
+        self.add_on_core(boot, process_1, Priority::Low);
+
+        // NOTE: releasing the secondary cores from their boot spin (writing the
+        // spin-table / mailbox addresses and having each enter `switch`) is out
+        // of scope for this change. Until that lands, only the boot core
+        // dispatches, its queue is never empty, and the per-core run queues and
+        // random work-stealing below are not exercised — they are in place for
+        // when core bring-up is added, not delivered, functioning SMP today.
 
         Controller::new().enable(Interrupt::Timer1);
         tick_in(TICK);
@@ -86,79 +555,301 @@ impl GlobalScheduler {
 #[derive(Debug)]
 struct Scheduler {
     processes: VecDeque<Process>,
+    policy: Box<dyn SchedulingPolicy>,
+    rng: XorShiftRng,
     current: Option<Id>,
-    last_id: Option<Id>,
 }
 
 impl Scheduler {
-    /// Returns a new `Scheduler` with an empty queue.
-    fn new() -> Scheduler {
+    /// Returns a new local `Scheduler` with an empty queue that orders
+    /// processes with `policy` and seeds its victim-selection RNG with `seed`.
+    fn new(policy: Box<dyn SchedulingPolicy>, seed: u32) -> Scheduler {
         Scheduler {
             processes: VecDeque::new(),
+            policy,
+            rng: XorShiftRng::new(seed),
             current: None,
-            last_id: None
         }
     }
 
-    /// Adds a process to the scheduler's queue and returns that process's ID if
-    /// a new process can be scheduled. The process ID is newly allocated for
-    /// the process and saved in its `trap_frame`. If no further processes can
-    /// be scheduled, returns `None`.
+    /// Registers an already-built `process` — with its stack and trap frame
+    /// pre-populated — under the pre-allocated `id`, recording that ID in its
+    /// `trap_frame.tpidr`. Everything else in the trap frame is left intact, so
+    /// this is the path `fork` uses to admit a child whose context has been
+    /// copied from its parent. Returns `id`.
     ///
-    /// If this is the first process added, it is marked as the current process.
-    /// It is the caller's responsibility to ensure that the first time `switch`
-    /// is called, that process is executing on the CPU.
-    fn add(&mut self, mut process: Process) -> Option<Id> {
-        let id = match self.last_id {
-            Some(last_id) => last_id.checked_add(1)?,
-            None => 0
-        };
-
+    /// If this is the first process added to the core, it is marked as the
+    /// current process.
+    fn add_process(&mut self, id: Id, mut process: Process) -> Option<Id> {
         process.trap_frame.tpidr = id;
+        self.policy.enqueue(id, &process);
         self.processes.push_back(process);
 
         if let None = self.current {
             self.current = Some(id);
         }
 
-        self.last_id = Some(id);
-        self.last_id
+        Some(id)
+    }
+
+    /// Returns a mutable reference to the stored process with the given `id`.
+    fn get_mut(&mut self, id: Id) -> Option<&mut Process> {
+        self.processes.iter_mut().find(|p| p.get_id() == id)
     }
 
-    /// Sets the current process's state to `new_state`, finds the next process
-    /// to switch to, and performs the context switch on `tf` by saving `tf`
-    /// into the current process and restoring the next process's trap frame
-    /// into `tf`. If there is no current process, returns `None`. Otherwise,
-    /// returns `Some` of the process ID that was context switched into `tf`.
+    /// Clones the process whose trap frame is `tf` into a fresh child admitted
+    /// under `id`. See the documentation on `GlobalScheduler::fork` for the
+    /// calling convention.
     ///
-    /// This method blocks until there is a process to switch to, conserving
-    /// energy as much as possible in the interim.
-    fn switch(&mut self, new_state: State, tf: &mut TrapFrame) -> Option<Id> {
-        let mut current = self.processes.pop_front()?;
-        let current_id = current.get_id();
-        current.trap_frame = Box::new(*tf);
-        current.state = new_state;
-        self.processes.push_back(current);
+    /// The child's stack is a byte-for-byte copy of the parent's live frame, so
+    /// the "distinct stacks" guarantee is only *physical*: any absolute pointer
+    /// saved into the copied region (the `x29` frame-pointer chain, `&local`s)
+    /// still aims at the parent's stack. This matches the classic `fork`
+    /// caveat and is safe for child code that does not dereference such saved
+    /// pointers before overwriting its own frame.
+    fn fork(&mut self, id: Id, tf: &TrapFrame) -> Option<Id> {
+        let parent_id = self.current?;
 
-        loop {
-            let mut process = self.processes.pop_front()?;
-            if process.is_ready() {
-                self.current = Some(process.get_id() as Id);
-                *tf = *process.trap_frame;
-                process.state = State::Running;
-
-                // Push process back into queue.
-                self.processes.push_front(process);
+        // A fresh process gives the child its own independent stack.
+        let mut child = Process::new()?;
+
+        // Mirror the parent's live stack onto the child's so the child resumes
+        // with identical locals but on its own memory.
+        let used = {
+            let parent = self.get_mut(parent_id)?;
+            let top = parent.stack.top().as_u64();
+            let bottom = parent.stack.bottom().as_u64();
+            // Reject a frame pointer that doesn't lie within the parent's
+            // stack: an unchecked `top - tf.sp` would underflow to a near-
+            // `u64::MAX` length and corrupt memory in `copy_nonoverlapping`.
+            if tf.sp < bottom || tf.sp > top {
+                return None;
+            }
+            let used = top - tf.sp;
+            unsafe {
+                let src = (parent.stack.top().as_u64() - used) as *const u8;
+                let dst = (child.stack.top().as_u64() - used) as *mut u8;
+                std::ptr::copy_nonoverlapping(src, dst, used as usize);
+            }
+            child.priority = parent.priority;
+            used
+        };
+
+        // Deep-copy the parent's execution context, then retarget the stack
+        // pointer at the mirrored frame and hand the child its `0` return.
+        child.trap_frame = Box::new(*tf);
+        child.trap_frame.sp = child.stack.top().as_u64() - used;
+        child.trap_frame.x0 = 0;
+        child.state = State::Ready;
+
+        self.add_process(id, child)
+    }
+
+    /// Saves the outgoing process's `tf` and `new_state` back into its queue
+    /// slot and lets the policy react to a timer preemption. Returns the
+    /// outgoing process's ID, or `None` if there is no current process.
+    fn park_current(&mut self, new_state: State, tf: &mut TrapFrame) -> Option<Id> {
+        let current_id = self.current?;
+        let exhausted = {
+            let current = self.get_mut(current_id)?;
+            current.trap_frame = Box::new(*tf);
+            current.state = new_state;
+            // A timer preemption (process still `Ready`) only counts as a full
+            // quantum if the slice it was dispatched with actually elapsed.
+            // The tickless timer can fire early to service another process's
+            // sleeper deadline; such an early wake must not be mistaken for
+            // slice exhaustion, or a CPU-bound process sharing a core with a
+            // frequently-sleeping one would be demoted on every early wake.
+            match new_state {
+                State::Ready => current_time() >= current.slice_start + TICK as u64,
+                _ => false,
+            }
+        };
+        // Only a process that truly exhausted its slice is penalized.
+        if exhausted {
+            self.policy.on_preempt(current_id);
+        }
+        Some(current_id)
+    }
+
+    /// Wakes any due sleepers, asks the policy for the next ready process, and
+    /// — if one exists — restores it into `tf`, marks it running, and arms the
+    /// timer tickless for the nearest upcoming event. Returns the dispatched
+    /// process's ID, or `None` if nothing local is ready.
+    ///
+    /// Ordering is delegated entirely to the `SchedulingPolicy`; the readiness
+    /// gating and tickless timer arming that keep dispatch correct live here,
+    /// independent of how the policy ranks processes.
+    fn pick(&mut self, tf: &mut TrapFrame) -> Option<Id> {
+        self.wake_sleepers();
+
+        // Borrow `processes` and `policy` as disjoint fields so the readiness
+        // closure can poll each process with `&mut self` (as `is_ready`
+        // requires) while the policy mutates its own state.
+        let Scheduler { ref mut processes, ref mut policy, .. } = *self;
+        let mut ready = |id: Id| processes.iter_mut()
+            .find(|p| p.get_id() == id)
+            .map_or(false, |p| p.is_ready());
+        let id = policy.next(&mut ready)?;
+
+        let now = current_time();
+        let process = self.get_mut(id).expect("policy returned unknown id");
+        self.current = Some(id);
+        *tf = *process.trap_frame;
+        process.state = State::Running;
+        // Stamp the start of this slice so `park_current` can tell a genuine
+        // slice exhaustion from an early tickless wake.
+        process.slice_start = now;
+
+        // Tickless re-arm: sleep only until the next event — the end of this
+        // slice or the earliest sleeper deadline.
+        self.arm_timer();
+
+        Some(id)
+    }
+
+    /// Removes and returns the back half of the run queue so another core can
+    /// adopt it. The currently-running process is never given away.
+    fn take_back_half(&mut self) -> Vec<Process> {
+        let count = self.processes.len() / 2;
+        let mut stolen = Vec::with_capacity(count);
+        while stolen.len() < count {
+            let process = match self.processes.pop_back() {
+                Some(process) => process,
+                None => break,
+            };
+            if Some(process.get_id()) == self.current {
+                // Don't migrate the running process; put it back and stop.
+                self.processes.push_back(process);
                 break;
-            } else if process.get_id() == current_id {
-                // We cycled the list, wait for an interrupt.
-                aarch64::wfi();
             }
+            self.policy.remove(process.get_id());
+            stolen.push(process);
+        }
+        stolen
+    }
+
+    /// Adopts a process migrated from another core, registering it with the
+    /// local policy. The process's state is preserved.
+    fn absorb(&mut self, process: Process) {
+        self.policy.enqueue(process.get_id(), &process);
+        if let None = self.current {
+            self.current = Some(process.get_id());
+        }
+        self.processes.push_back(process);
+    }
 
-            self.processes.push_back(process);
+    /// Flips every sleeping process whose deadline the system timer has passed
+    /// back to `State::Ready`.
+    fn wake_sleepers(&mut self) {
+        let now = current_time();
+        for process in self.processes.iter_mut() {
+            if let State::Sleeping { until } = process.state {
+                if now >= until {
+                    process.state = State::Ready;
+                }
+            }
         }
+    }
 
-        self.current
+    /// Reports whether any queued process is currently runnable.
+    fn has_ready(&mut self) -> bool {
+        self.processes.iter_mut().any(|p| p.is_ready())
+    }
+
+    /// Returns the earliest sleeper wake deadline, or `None` if no process is
+    /// currently sleeping.
+    fn next_wake(&self) -> Option<u64> {
+        self.processes
+            .iter()
+            .filter_map(|process| match process.state {
+                State::Sleeping { until } => Some(until),
+                _ => None,
+            })
+            .min()
+    }
+
+    /// Programs the timer for the nearest upcoming event: `min(TICK, next_wake
+    /// - now)`. With no sleepers this degrades to a plain `TICK` slice.
+    fn arm_timer(&self) {
+        let us = match self.next_wake() {
+            Some(until) => {
+                let now = current_time();
+                // Clamp to a non-zero minimum so a deadline already in the past
+                // still arms a real interrupt rather than `tick_in(0)`.
+                let delta = until.saturating_sub(now).min(TICK as u64).max(1);
+                delta as u32
+            }
+            None => TICK,
+        };
+        tick_in(us);
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a local scheduler holding a single ready process admitted under
+    /// `id` `0`, returning the scheduler and a trap frame whose `sp` points
+    /// into that process's live stack.
+    fn scheduler_with_parent() -> (Scheduler, Id, TrapFrame) {
+        let mut sched = Scheduler::new(Box::new(RoundRobinPolicy::new()), 1);
+        let parent = Process::new().expect("parent process");
+        let parent_id = sched.add_process(0, parent).expect("parent added");
+
+        let mut tf = TrapFrame::default();
+        tf.sp = sched.get_mut(parent_id).unwrap().stack.top().as_u64() - 16;
+        tf.elr = 0xdead_beef;
+        (sched, parent_id, tf)
+    }
+
+    #[test]
+    fn fork_child_has_distinct_id_and_stack() {
+        let (mut sched, parent_id, tf) = scheduler_with_parent();
+        let parent_top = sched.get_mut(parent_id).unwrap().stack.top().as_u64();
+
+        let child_id = sched.fork(1, &tf).expect("fork succeeds");
+        assert_ne!(child_id, parent_id);
+
+        let child = sched.get_mut(child_id).unwrap();
+        // The child id is recorded in `tpidr`, and it resumes at the same `elr`
+        // as the parent but reading `0` from `x0`.
+        assert_eq!(child.get_id(), child_id);
+        assert_eq!(child.trap_frame.tpidr, child_id);
+        assert_eq!(child.trap_frame.x0, 0);
+        assert_eq!(child.trap_frame.elr, tf.elr);
+
+        // The child owns a distinct stack, and its `sp` is retargeted onto that
+        // stack at the same offset from the top.
+        let child_top = child.stack.top().as_u64();
+        assert_ne!(child_top, parent_top);
+        assert_eq!(child.trap_frame.sp, child_top - 16);
+    }
+
+    #[test]
+    fn fork_leaves_parent_frame_untouched() {
+        let (mut sched, parent_id, tf) = scheduler_with_parent();
+        let before = *sched.get_mut(parent_id).unwrap().trap_frame;
+
+        sched.fork(1, &tf).expect("fork succeeds");
+
+        let after = *sched.get_mut(parent_id).unwrap().trap_frame;
+        assert_eq!(before.sp, after.sp);
+        assert_eq!(before.x0, after.x0);
+        assert_eq!(before.elr, after.elr);
+    }
+
+    #[test]
+    fn fork_rejects_out_of_bounds_sp() {
+        let (mut sched, parent_id, mut tf) = scheduler_with_parent();
+
+        // `sp` above the stack top would underflow the copy length; rejected.
+        tf.sp = sched.get_mut(parent_id).unwrap().stack.top().as_u64() + 16;
+        assert!(sched.fork(1, &tf).is_none());
+
+        // `sp` below the stack bottom is equally out of range.
+        tf.sp = sched.get_mut(parent_id).unwrap().stack.bottom().as_u64() - 16;
+        assert!(sched.fork(1, &tf).is_none());
+    }
+}